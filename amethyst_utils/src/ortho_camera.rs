@@ -1,18 +1,215 @@
+use amethyst_assets::{PrefabData, PrefabError};
 use amethyst_core::Axis2;
 use amethyst_core::cgmath::Ortho;
-use amethyst_core::specs::{Component, DenseVecStorage, Join, System, ReadExpect, ReadStorage, WriteStorage};
+use amethyst_core::specs::{Component, DenseVecStorage, Entity, Join, System, ReadExpect, ReadStorage, WriteStorage};
 use amethyst_renderer::{Camera, ScreenDimensions};
+use serde::{Deserialize, Serialize};
+
+/// The region of world space the camera keeps visible.
+/// Expressed as the four edges of the rectangle that maps to the window before normalization.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorldCoordinates {
+    /// Left edge of the visible world region.
+    pub left: f32,
+    /// Right edge of the visible world region.
+    pub right: f32,
+    /// Bottom edge of the visible world region.
+    pub bottom: f32,
+    /// Top edge of the visible world region.
+    pub top: f32,
+}
+
+impl WorldCoordinates {
+    /// Creates a new region from its four edges.
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        WorldCoordinates {
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// The `[0,1]` region used before world coordinates were configurable.
+    pub fn normalized() -> Self {
+        WorldCoordinates::new(0.0, 1.0, 0.0, 1.0)
+    }
+
+    /// Width of the region in world units.
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    /// Height of the region in world units.
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    /// Aspect ratio (`width/height`) of the region.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width() / self.height()
+    }
+}
+
+impl Default for WorldCoordinates {
+    fn default() -> Self {
+        WorldCoordinates::normalized()
+    }
+}
+
+/// Decides how the visible world region is computed from the window dimensions, independently of the
+/// aspect-ratio handling in `CameraNormalizeMode`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ScalingMode {
+    /// Normalize the `world_coordinates` region using the `mode` field (`Lossy`/`Shrink`/`Contain`). This is
+    /// the default and reproduces the historical behavior.
+    Normalized,
+    /// Keep the region's vertical extent constant and widen/narrow the horizontal extent by the
+    /// window aspect ratio.
+    FixedVertical,
+    /// Keep the region's horizontal extent constant and widen/narrow the vertical extent by the
+    /// window aspect ratio.
+    FixedHorizontal,
+    /// Map one world unit to one screen pixel using `ScreenDimensions`, centered on the region.
+    WindowSize,
+    /// Leave the region untouched, exactly matching `world_coordinates`.
+    None,
+}
+
+impl Default for ScalingMode {
+    fn default() -> Self {
+        ScalingMode::Normalized
+    }
+}
 
 /// `Component` attached to the camera's entity that allows automatically adjusting the camera's matrix according to preferences in the "mode" field.
-#[derive(Default)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NormalOrthoCamera {
     /// How the camera's matrix is changed when the window's aspect ratio changes. See `CameraNormalizeMode` for more info.
     pub mode: CameraNormalizeMode,
+    /// How the visible world region is derived from the window dimensions. See `ScalingMode`.
+    pub scaling_mode: ScalingMode,
+    /// Zoom factor applied about the region center. `1.0` leaves the region unchanged, larger
+    /// values zoom out and smaller values zoom in, without translating the view.
+    pub scale: f32,
+    /// The world space region the camera keeps visible. Defaults to the `[0,1]` range on both axes.
+    pub world_coordinates: WorldCoordinates,
+    /// Near clip plane. Defaults to `-1000.0` so sprites at low `z` are not clipped behind the camera.
+    pub near: f32,
+    /// Far clip plane. Defaults to `1000.0`.
+    pub far: f32,
+    /// Snaps the computed ortho offsets to whole-pixel boundaries to avoid sub-pixel texel shimmer
+    /// when the window resizes. Useful for pixel-art games. Defaults to `false`.
+    pub pixel_perfect: bool,
+}
+
+impl Default for NormalOrthoCamera {
+    fn default() -> Self {
+        NormalOrthoCamera {
+            mode: CameraNormalizeMode::default(),
+            scaling_mode: ScalingMode::default(),
+            scale: 1.0,
+            world_coordinates: WorldCoordinates::default(),
+            near: -1000.0,
+            far: 1000.0,
+            pixel_perfect: false,
+        }
+    }
 }
 
 impl NormalOrthoCamera {
-    pub fn camera_offsets(&self, ratio: f32) -> (f32,f32,f32,f32) {
-        self.mode.camera_offsets(ratio)
+    /// Computes the four ortho offsets (`left`, `right`, `bottom`, `top`) for the given window size in
+    /// pixels, applying both the `scaling_mode` and the `scale` zoom factor.
+    pub fn camera_offsets(&self, screen_width: f32, screen_height: f32) -> (f32,f32,f32,f32) {
+        let aspect_ratio = screen_width / screen_height;
+        let world = &self.world_coordinates;
+        let offsets = match self.scaling_mode {
+            ScalingMode::Normalized => self.mode.camera_offsets(aspect_ratio, world),
+            ScalingMode::FixedVertical => CameraNormalizeMode::lossy_x(aspect_ratio, world),
+            ScalingMode::FixedHorizontal => CameraNormalizeMode::lossy_y(aspect_ratio, world),
+            ScalingMode::WindowSize => {
+                let center_x = (world.left + world.right) / 2.0;
+                let center_y = (world.bottom + world.top) / 2.0;
+                let half_width = screen_width / 2.0;
+                let half_height = screen_height / 2.0;
+                (
+                    center_x - half_width,
+                    center_x + half_width,
+                    center_y - half_height,
+                    center_y + half_height,
+                )
+            },
+            ScalingMode::None => (world.left, world.right, world.bottom, world.top),
+        };
+        // Zoom about the region center so a non-origin-centered `world_coordinates` does not drift.
+        let center_x = (offsets.0 + offsets.1) / 2.0;
+        let center_y = (offsets.2 + offsets.3) / 2.0;
+        let offsets = (
+            center_x + (offsets.0 - center_x) * self.scale,
+            center_x + (offsets.1 - center_x) * self.scale,
+            center_y + (offsets.2 - center_y) * self.scale,
+            center_y + (offsets.3 - center_y) * self.scale,
+        );
+        if self.pixel_perfect {
+            NormalOrthoCamera::snap_to_pixels(offsets, screen_width, screen_height)
+        } else {
+            offsets
+        }
+    }
+
+    /// Snaps the four edges to whole-pixel boundaries so the projection stays aligned to the pixel
+    /// grid. Each edge is converted to pixels, rounded, and converted back; `left`/`bottom` absorb
+    /// the rounding remainder so that `(right-left)` and `(top-bottom)` span an exact number of pixels.
+    fn snap_to_pixels(offsets: (f32,f32,f32,f32), screen_width: f32, screen_height: f32) -> (f32,f32,f32,f32) {
+        let (left, right, bottom, top) = offsets;
+        let pixels_per_unit_x = screen_width / (right - left);
+        let pixels_per_unit_y = screen_height / (top - bottom);
+
+        let left_px = (left * pixels_per_unit_x).round();
+        let right_px = (right * pixels_per_unit_x).round();
+        let bottom_px = (bottom * pixels_per_unit_y).round();
+        let top_px = (top * pixels_per_unit_y).round();
+
+        let left = left_px / pixels_per_unit_x;
+        let bottom = bottom_px / pixels_per_unit_y;
+        let right = left + (right_px - left_px) / pixels_per_unit_x;
+        let top = bottom + (top_px - bottom_px) / pixels_per_unit_y;
+        (left, right, bottom, top)
+    }
+
+    /// Computes the pixel viewport rectangle (`x`, `y`, `width`, `height`) for the given window size.
+    ///
+    /// Returns `Some` centered, aspect-preserving rectangle only when the `mode` is
+    /// `CameraNormalizeMode::Contain`, and `None` for every other mode (meaning the whole window is
+    /// used). This does not change the projection, which under `ScalingMode::Normalized` stays at the
+    /// design region (zoomed by `scale`); pass this rectangle to the render target's viewport/scissor
+    /// to letterbox/pillarbox the output so the un-rendered margins show the clear color as bars.
+    pub fn viewport(&self, screen_width: f32, screen_height: f32) -> Option<(f32,f32,f32,f32)> {
+        if let CameraNormalizeMode::Contain = self.mode {
+            let design_ratio = self.world_coordinates.aspect_ratio();
+            let window_ratio = screen_width / screen_height;
+            if window_ratio > design_ratio {
+                let viewport_width = screen_height * design_ratio;
+                Some((
+                    (screen_width - viewport_width) / 2.0,
+                    0.0,
+                    viewport_width,
+                    screen_height,
+                ))
+            } else if window_ratio < design_ratio {
+                let viewport_height = screen_width / design_ratio;
+                Some((
+                    0.0,
+                    (screen_height - viewport_height) / 2.0,
+                    screen_width,
+                    viewport_height,
+                ))
+            } else {
+                Some((0.0, 0.0, screen_width, screen_height))
+            }
+        } else {
+            None
+        }
     }
 }
 
@@ -20,7 +217,23 @@ impl Component for NormalOrthoCamera {
     type Storage = DenseVecStorage<Self>;
 }
 
+impl<'a> PrefabData<'a> for NormalOrthoCamera {
+    type SystemData = WriteStorage<'a, NormalOrthoCamera>;
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        storage: &mut Self::SystemData,
+        _entities: &[Entity],
+    ) -> Result<(), PrefabError> {
+        storage.insert(entity, self.clone())?;
+        Ok(())
+    }
+}
+
 /// Settings that decide how to scale the camera's matrix when the aspect ratio changes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum CameraNormalizeMode {
     /// Using an aspect ratio of 1:1, tries to ajust the matrix values of the camera so
     /// that the direction opposite to the stretch_direction is always [0,1].
@@ -29,42 +242,56 @@ pub enum CameraNormalizeMode {
     
     /// Scales the render dynamically to ensure no space is lost in the [0,1] range on any axis.
     Shrink,
+
+    /// Keeps the camera matrix at the exact world region and preserves the region's own aspect
+    /// ratio (`world_coordinates.aspect_ratio()`) by shrinking the rendered viewport and leaving
+    /// bars, rather than stretching or gaining/losing world space. Too-wide windows get vertical
+    /// bars on the sides (pillarbox); too-tall windows get horizontal bars (letterbox).
+    /// The bar rectangle is computed by `NormalOrthoCamera::viewport`; `NormalOrthoCameraSystem`
+    /// only sets the projection and does not apply that rectangle to the render target.
+    Contain,
 }
 
 impl CameraNormalizeMode {
-    /// Get the camera matrix offsets according to the specified options.
-    pub fn camera_offsets(&self, aspect_ratio: f32) -> (f32,f32,f32,f32) {
+    /// Get the camera matrix offsets according to the specified options and the visible world region.
+    pub fn camera_offsets(&self, aspect_ratio: f32, world: &WorldCoordinates) -> (f32,f32,f32,f32) {
         match self {
             &CameraNormalizeMode::Lossy {ref stretch_direction} => {
                 match stretch_direction {
                     Axis2::X => {
-                        CameraNormalizeMode::lossy_x(aspect_ratio)
+                        CameraNormalizeMode::lossy_x(aspect_ratio, world)
                     },
                     Axis2::Y => {
-                        CameraNormalizeMode::lossy_y(aspect_ratio)
+                        CameraNormalizeMode::lossy_y(aspect_ratio, world)
                     },
                 }
             },
             &CameraNormalizeMode::Shrink => {
-                if aspect_ratio > 1.0 {
-                    CameraNormalizeMode::lossy_x(aspect_ratio)
-                } else if aspect_ratio < 1.0 {
-                    CameraNormalizeMode::lossy_y(aspect_ratio)
+                let world_ratio = world.aspect_ratio();
+                if aspect_ratio > world_ratio {
+                    CameraNormalizeMode::lossy_x(aspect_ratio, world)
+                } else if aspect_ratio < world_ratio {
+                    CameraNormalizeMode::lossy_y(aspect_ratio, world)
                 } else {
-                    (0.0,1.0,0.0,1.0)
+                    (world.left, world.right, world.bottom, world.top)
                 }
             },
+            &CameraNormalizeMode::Contain => {
+                (world.left, world.right, world.bottom, world.top)
+            },
         }
     }
-    
-    fn lossy_x(aspect_ratio: f32) -> (f32,f32,f32,f32) {
-        let offset = (aspect_ratio - 1.0) / 2.0;
-        (-offset, 1.0 + offset, 0.0, 1.0)
+
+    fn lossy_x(aspect_ratio: f32, world: &WorldCoordinates) -> (f32,f32,f32,f32) {
+        let center = (world.left + world.right) / 2.0;
+        let half_width = world.height() * aspect_ratio / 2.0;
+        (center - half_width, center + half_width, world.bottom, world.top)
     }
 
-    fn lossy_y(aspect_ratio: f32) -> (f32,f32,f32,f32) {
-        let offset = (1.0 / aspect_ratio - 1.0) / 2.0;
-        (0.0, 1.0, -offset, 1.0 + offset)
+    fn lossy_y(aspect_ratio: f32, world: &WorldCoordinates) -> (f32,f32,f32,f32) {
+        let center = (world.bottom + world.top) / 2.0;
+        let half_height = world.width() / aspect_ratio / 2.0;
+        (world.left, world.right, center - half_height, center + half_height)
     }
 }
 
@@ -76,28 +303,134 @@ impl Default for CameraNormalizeMode {
 
 /// System that automatically changes the camera matrix according to the settings in the `NormalOrthoCamera` attached to the camera entity.
 #[derive(Default)]
-pub struct NormalOrthoCameraSystem {
-    aspect_ratio_cache: f32,
-}
+pub struct NormalOrthoCameraSystem;
 
 impl<'a> System<'a> for NormalOrthoCameraSystem {
     type SystemData = (ReadExpect<'a, ScreenDimensions>, WriteStorage<'a, Camera>, ReadStorage<'a, NormalOrthoCamera>);
     fn run(&mut self, (dimensions, mut cameras, ortho_cameras): Self::SystemData) {
-        let aspect = dimensions.aspect_ratio();
-        if aspect != self.aspect_ratio_cache {
-            self.aspect_ratio_cache = aspect;
-
-            for (mut camera, ortho_camera) in (&mut cameras, &ortho_cameras).join() {
-                let offsets = ortho_camera.camera_offsets(aspect);
-                camera.proj = Ortho {
-                    left: offsets.0,
-                    right: offsets.1,
-                    bottom: offsets.2,
-                    top: offsets.3,
-                    near: 0.1,
-                    far: 1000.0,
-                }.into();
-            }
+        // Recompute unconditionally: besides the window size, `scale`, `scaling_mode`, `mode` and
+        // `world_coordinates` can all change at runtime and a size-only cache would miss them.
+        let width = dimensions.width();
+        let height = dimensions.height();
+        for (mut camera, ortho_camera) in (&mut cameras, &ortho_cameras).join() {
+            // The projection is always left at the region the camera computes, which under `Contain`
+            // with `ScalingMode::Normalized` is the design region (zoomed by `scale`). Bars are
+            // produced by applying `ortho_camera.viewport()` to the render target; padding the
+            // matrix would gain world space instead of bars.
+            let offsets = ortho_camera.camera_offsets(width, height);
+            camera.proj = Ortho {
+                left: offsets.0,
+                right: offsets.1,
+                bottom: offsets.2,
+                top: offsets.3,
+                near: ortho_camera.near,
+                far: ortho_camera.far,
+            }.into();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_round_trip_default() {
+        let camera = NormalOrthoCamera::default();
+        let serialized = ron::ser::to_string(&camera).unwrap();
+        let deserialized: NormalOrthoCamera = ron::de::from_str(&serialized).unwrap();
+
+        assert!(match deserialized.mode {
+            CameraNormalizeMode::Shrink => true,
+            _ => false,
+        });
+        assert!(match deserialized.scaling_mode {
+            ScalingMode::Normalized => true,
+            _ => false,
+        });
+        assert_eq!(deserialized.scale, 1.0);
+        assert_eq!(deserialized.near, -1000.0);
+        assert_eq!(deserialized.far, 1000.0);
+        assert!(!deserialized.pixel_perfect);
+    }
+
+    fn assert_offsets(actual: (f32,f32,f32,f32), expected: (f32,f32,f32,f32)) {
+        assert!((actual.0 - expected.0).abs() < 1e-3, "left: {} != {}", actual.0, expected.0);
+        assert!((actual.1 - expected.1).abs() < 1e-3, "right: {} != {}", actual.1, expected.1);
+        assert!((actual.2 - expected.2).abs() < 1e-3, "bottom: {} != {}", actual.2, expected.2);
+        assert!((actual.3 - expected.3).abs() < 1e-3, "top: {} != {}", actual.3, expected.3);
+    }
+
+    #[test]
+    fn lossy_x_uses_region_aspect() {
+        let world = WorldCoordinates::new(0.0, 1920.0, 0.0, 1080.0);
+        // Visible width tracks the window aspect against the region's own height, centered on the region.
+        assert_offsets(CameraNormalizeMode::lossy_x(2.0, &world), (-120.0, 2040.0, 0.0, 1080.0));
+    }
+
+    #[test]
+    fn lossy_y_uses_region_aspect() {
+        let world = WorldCoordinates::new(0.0, 1920.0, 0.0, 1080.0);
+        assert_offsets(CameraNormalizeMode::lossy_y(2.0, &world), (0.0, 1920.0, 60.0, 1020.0));
+    }
+
+    #[test]
+    fn shrink_branches_on_world_ratio() {
+        let world = WorldCoordinates::new(0.0, 1920.0, 0.0, 1080.0);
+        let shrink = CameraNormalizeMode::Shrink;
+        // Window wider than the 16:9 region stretches horizontally (lossy_x) ...
+        assert_offsets(shrink.camera_offsets(2.0, &world), CameraNormalizeMode::lossy_x(2.0, &world));
+        // ... narrower stretches vertically (lossy_y).
+        assert_offsets(shrink.camera_offsets(1.0, &world), CameraNormalizeMode::lossy_y(1.0, &world));
+    }
+
+    #[test]
+    fn snap_to_pixels_spans_whole_pixels() {
+        let offsets = (-0.123, 0.877, 0.0, 1.0);
+        let (screen_width, screen_height) = (200.0, 100.0);
+        let pixels_per_unit_x = screen_width / (offsets.1 - offsets.0);
+        let pixels_per_unit_y = screen_height / (offsets.3 - offsets.2);
+
+        let snapped = NormalOrthoCamera::snap_to_pixels(offsets, screen_width, screen_height);
+
+        let width_px = (snapped.1 - snapped.0) * pixels_per_unit_x;
+        let height_px = (snapped.3 - snapped.2) * pixels_per_unit_y;
+        assert!((width_px - width_px.round()).abs() < 1e-3, "width not whole pixels: {}", width_px);
+        assert!((height_px - height_px.round()).abs() < 1e-3, "height not whole pixels: {}", height_px);
+    }
+
+    #[test]
+    fn viewport_pillarbox_and_letterbox() {
+        let mut camera = NormalOrthoCamera::default();
+        camera.mode = CameraNormalizeMode::Contain;
+        // A 2:1 design region drives the preserved ratio.
+        camera.world_coordinates = WorldCoordinates::new(0.0, 2.0, 0.0, 1.0);
+
+        // Too-wide window: centered pillarbox with side bars.
+        assert_offsets(camera.viewport(400.0, 100.0).unwrap(), (100.0, 0.0, 200.0, 100.0));
+        // Too-tall window: centered letterbox with top/bottom bars.
+        assert_offsets(camera.viewport(100.0, 100.0).unwrap(), (0.0, 25.0, 100.0, 50.0));
+    }
+
+    #[test]
+    fn viewport_none_without_framing_mode() {
+        let camera = NormalOrthoCamera::default();
+        assert!(camera.viewport(400.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn serde_round_trip_lossy_axis() {
+        let mode = CameraNormalizeMode::Lossy {
+            stretch_direction: Axis2::X,
+        };
+        let serialized = ron::ser::to_string(&mode).unwrap();
+        let deserialized: CameraNormalizeMode = ron::de::from_str(&serialized).unwrap();
+
+        assert!(match deserialized {
+            CameraNormalizeMode::Lossy {
+                stretch_direction: Axis2::X,
+            } => true,
+            _ => false,
+        });
+    }
+}